@@ -1,24 +1,161 @@
+// The `wasm_bindgen` bindings pull in `std` and the wasm toolchain, neither of
+// which is available in a bare-metal / tiny-WASM build. Under the `no_std`
+// feature this whole browser-facing layer is compiled out so it never drags
+// `std` into a `--no-default-features` build of the core; when the feature is
+// set the module is empty, which is a valid crate on its own.
+//
+// NOTE: this only delivers the wasm-layer half of chunk0-6. The `DenoiseState`
+// core and frame pipeline in the crate root still need their own `core`+`alloc`
+// +`libm` port before `no_std` yields a buildable core; that work lives in the
+// crate root, which is not part of this tree, so the feature is not yet usable
+// end-to-end.
+//
+// `no_std` is declared in the crate root's `[features]`; when this module is
+// compiled in isolation (no manifest) that declaration isn't visible, so quiet
+// the resulting `unexpected_cfgs` lint rather than fail `-D warnings`.
+#![allow(unexpected_cfgs)]
+#![cfg(not(feature = "no_std"))]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
 use wasm_bindgen::prelude::*;
-use crate::{DenoiseState, FRAME_SIZE};
+use crate::{DenoiseState, RnnModel, FRAME_SIZE};
+
+thread_local! {
+    // Parsed custom models, keyed by a hash of their raw blob. `DenoiseState`
+    // borrows the model for `'static`, so the model has to outlive every state
+    // built from it; caching here bounds the leak to one parsed model per
+    // distinct blob (keyed by hash, not a retained copy of the blob), so
+    // reconnect / re-init / model-swap with the same weights reuses it instead
+    // of leaking on every call. Distinct models are still never reclaimed —
+    // see the public docs on `fromModel`.
+    static MODEL_CACHE: RefCell<HashMap<u64, &'static RnnModel>> =
+        RefCell::new(HashMap::new());
+}
+
+fn blob_key(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+// One denoiser plus its scratch frame buffers. RNNoise state is per-channel
+// and must never be shared, so interleaved streams get one of these each.
+struct ChannelDenoiser {
+    state: Box<DenoiseState<'static>>,
+    frame_chunk: Vec<f32>,
+    out_chunk: Vec<f32>,
+}
+
+impl ChannelDenoiser {
+    fn new() -> Self {
+        Self {
+            state: DenoiseState::new(),
+            frame_chunk: vec![0.0; FRAME_SIZE],
+            out_chunk: vec![0.0; FRAME_SIZE],
+        }
+    }
+
+    fn from_model(model: &'static RnnModel) -> Self {
+        Self {
+            state: DenoiseState::from_model(model),
+            frame_chunk: vec![0.0; FRAME_SIZE],
+            out_chunk: vec![0.0; FRAME_SIZE],
+        }
+    }
+}
+
+// Denoised frame paired with its VAD probability, returned by
+// `process_frame_with_vad`.
+#[wasm_bindgen]
+pub struct VadResult {
+    samples: Vec<f32>,
+    vad: f32,
+}
+
+#[wasm_bindgen]
+impl VadResult {
+    #[wasm_bindgen(getter)]
+    pub fn samples(&self) -> Vec<f32> {
+        self.samples.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn vad(&self) -> f32 {
+        self.vad
+    }
+}
+
 #[wasm_bindgen]
 pub struct WasmDenoiseState {
-    state: Box<DenoiseState<'static>>,
+    channels: Vec<ChannelDenoiser>,
+    // Samples accumulated across `push_samples` calls that did not fill a
+    // whole frame yet (always fewer than FRAME_SIZE).
+    leftover: Vec<f32>,
 }
 
 #[wasm_bindgen]
 impl WasmDenoiseState {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
+        Self::with_channels(1)
+    }
+
+    // Construct a denoiser holding `n` independent RNNoise states, one per
+    // channel of an interleaved stream. Use with `process_interleaved`.
+    #[wasm_bindgen(js_name = withChannels)]
+    pub fn with_channels(n: usize) -> Self {
+        if n == 0 {
+            panic!("Channel count must be at least 1");
+        }
+
         Self {
-            state: DenoiseState::new(),
+            channels: (0..n).map(|_| ChannelDenoiser::new()).collect(),
+            leftover: Vec::with_capacity(FRAME_SIZE),
         }
     }
 
+    // Construct a mono denoiser from a custom-trained RNNoise model blob,
+    // letting deployments ship a domain-tuned model without recompiling. Throws
+    // if the blob cannot be parsed.
+    //
+    // Memory note: a parsed model lives for the rest of the process. Loads of
+    // the same blob are deduplicated and cost nothing extra, but each *distinct*
+    // model is retained permanently — `DenoiseState` borrows it for `'static`.
+    // Callers that hot-swap many different models should expect the total to
+    // grow with the number of unique models, not the number of `fromModel`
+    // calls.
+    #[wasm_bindgen(js_name = fromModel)]
+    pub fn from_model(bytes: &[u8]) -> Result<WasmDenoiseState, JsValue> {
+        let key = blob_key(bytes);
+        let model = MODEL_CACHE.with(|cache| {
+            if let Some(model) = cache.borrow().get(&key) {
+                return Ok(*model);
+            }
+
+            let parsed = RnnModel::from_bytes(bytes)
+                .map_err(|e| JsValue::from_str(&format!("invalid RNNoise model: {}", e)))?;
+
+            // `DenoiseState` borrows the model for `'static`; leak it once and
+            // cache it so subsequent loads of the same blob reuse it.
+            let model: &'static RnnModel = Box::leak(Box::new(parsed));
+            cache.borrow_mut().insert(key, model);
+            Ok(model)
+        })?;
+
+        Ok(Self {
+            channels: vec![ChannelDenoiser::from_model(model)],
+            leftover: Vec::with_capacity(FRAME_SIZE),
+        })
+    }
+
     #[wasm_bindgen]
     pub fn process_frame(&mut self, input: &[f32]) -> Vec<f32> {
         if input.len() != FRAME_SIZE {
@@ -26,10 +163,117 @@ impl WasmDenoiseState {
         }
 
         let mut output = vec![0.0; FRAME_SIZE];
-        self.state.process_frame(&mut output, input);
+        self.channels[0].state.process_frame(&mut output, input);
         output
     }
 
+    // Like `process_frame`, but also returns the voice-activity-detection
+    // probability in [0, 1] that RNNoise computes for the frame. Useful as a
+    // cheap speech gate for mute detection or push-to-talk.
+    #[wasm_bindgen(js_name = processFrameWithVad)]
+    pub fn process_frame_with_vad(&mut self, input: &[f32]) -> VadResult {
+        if input.len() != FRAME_SIZE {
+            panic!("Input frame must be exactly {} samples", FRAME_SIZE);
+        }
+
+        let mut output = vec![0.0; FRAME_SIZE];
+        let vad = self.channels[0].state.process_frame(&mut output, input);
+        VadResult {
+            samples: output,
+            vad,
+        }
+    }
+
+    // Denoise an interleaved multi-channel buffer (L/R/... per sample). The
+    // length must be a multiple of the channel count times FRAME_SIZE. Each
+    // channel is deinterleaved into its own frame buffer, run through its own
+    // denoiser, and re-interleaved into the output.
+    #[wasm_bindgen(js_name = processInterleaved)]
+    pub fn process_interleaved(&mut self, input: &[f32]) -> Vec<f32> {
+        let n = self.channels.len();
+        if input.len() % (n * FRAME_SIZE) != 0 {
+            panic!(
+                "Interleaved input must be a multiple of {} samples ({} channels x {})",
+                n * FRAME_SIZE,
+                n,
+                FRAME_SIZE
+            );
+        }
+
+        let mut output = vec![0.0; input.len()];
+        let frames = input.len() / (n * FRAME_SIZE);
+
+        for f in 0..frames {
+            let base = f * n * FRAME_SIZE;
+            let block = &input[base..base + n * FRAME_SIZE];
+            let out_block = &mut output[base..base + n * FRAME_SIZE];
+            for (ch, denoiser) in self.channels.iter_mut().enumerate() {
+                deinterleave_frame(block, n, ch, &mut denoiser.frame_chunk);
+                denoiser
+                    .state
+                    .process_frame(&mut denoiser.out_chunk, &denoiser.frame_chunk);
+                interleave_frame(&denoiser.out_chunk, n, ch, out_block);
+            }
+        }
+
+        output
+    }
+
+    // Feed an arbitrary-length block of samples and get back the denoised
+    // output for every whole frame that became available. Samples that do not
+    // fill a frame are retained internally and consumed by the next call, so
+    // the caller can push Web Audio blocks (128/256/1024 samples) directly
+    // without zero-padding in the middle of a continuous stream.
+    //
+    // Mono only: the streaming buffer has a single leftover queue, so this
+    // rejects instances built with `withChannels(n > 1)` — use
+    // `process_interleaved` for multi-channel audio.
+    #[wasm_bindgen]
+    pub fn push_samples(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.channels.len() != 1 {
+            panic!("push_samples is mono-only; use process_interleaved for multi-channel audio");
+        }
+
+        self.leftover.extend_from_slice(input);
+
+        let frames = self.leftover.len() / FRAME_SIZE;
+        let mut output = Vec::with_capacity(frames * FRAME_SIZE);
+        let mut frame = vec![0.0; FRAME_SIZE];
+
+        let mut consumed = 0;
+        for _ in 0..frames {
+            frame.copy_from_slice(&self.leftover[consumed..consumed + FRAME_SIZE]);
+            let processed = self.process_frame(&frame);
+            output.extend_from_slice(&processed);
+            consumed += FRAME_SIZE;
+        }
+
+        self.leftover.drain(..consumed);
+        output
+    }
+
+    // Denoise and return any samples still buffered, zero-padding the final
+    // partial frame up to FRAME_SIZE. Call this once at the end of a stream.
+    // Mono only, like `push_samples`.
+    #[wasm_bindgen]
+    pub fn flush(&mut self) -> Vec<f32> {
+        if self.channels.len() != 1 {
+            panic!("flush is mono-only; use process_interleaved for multi-channel audio");
+        }
+
+        if self.leftover.is_empty() {
+            return Vec::new();
+        }
+
+        let valid = self.leftover.len();
+        let mut frame = vec![0.0; FRAME_SIZE];
+        frame[..valid].copy_from_slice(&self.leftover);
+        self.leftover.clear();
+
+        let processed = self.process_frame(&frame);
+        processed[..valid].to_vec()
+    }
+
     #[wasm_bindgen(js_name = getFrameSize)]
     pub fn get_frame_size() -> usize {
         FRAME_SIZE
@@ -69,12 +313,187 @@ pub fn process_frame(state: *mut WasmDenoiseState, input: &[f32], output: &mut [
 
     unsafe {
         let state = &mut *state;
-        state.state.process_frame(output, input);
+        state.channels[0].state.process_frame(output, input);
     }
-    
+
     0
 }
 
+// Pointer-based variant of `process_frame` that returns the VAD probability
+// in [0, 1] on success, or a negative sentinel (-1 null state, -2 bad length)
+// on error.
+#[wasm_bindgen]
+pub fn process_frame_with_vad(state: *mut WasmDenoiseState, input: &[f32], output: &mut [f32]) -> f32 {
+    if state.is_null() {
+        return -1.0;
+    }
+
+    if input.len() != FRAME_SIZE || output.len() != FRAME_SIZE {
+        return -2.0;
+    }
+
+    unsafe {
+        let state = &mut *state;
+        state.channels[0].state.process_frame(output, input)
+    }
+}
+
+// Copy channel `ch` out of one interleaved block (`channels * FRAME_SIZE`
+// samples) into a contiguous per-channel frame buffer.
+fn deinterleave_frame(block: &[f32], channels: usize, ch: usize, dst: &mut [f32]) {
+    for (i, sample) in dst.iter_mut().enumerate() {
+        *sample = block[i * channels + ch];
+    }
+}
+
+// Write a contiguous per-channel frame back into channel `ch` of an
+// interleaved output block.
+fn interleave_frame(src: &[f32], channels: usize, ch: usize, block: &mut [f32]) {
+    for (i, sample) in src.iter().enumerate() {
+        block[i * channels + ch] = *sample;
+    }
+}
+
+// RNNoise only operates correctly at this rate; everything else is resampled
+// to it and back.
+const INTERNAL_SAMPLE_RATE: u32 = 48_000;
+
+// Stateful cubic (Catmull-Rom) interpolating resampler. Keeps a few trailing
+// input samples between calls so a continuous stream can be fed in arbitrary
+// chunks without discontinuities at the boundaries.
+//
+// Quality caveat: this is a bare interpolator with no anti-alias lowpass. When
+// downsampling (48 kHz -> a lower output rate), any high-frequency noise
+// residue above the output Nyquist folds back into the audible band. That is
+// acceptable for speech — the intended use — but callers needing clean
+// downsampling of wideband content should prefilter first.
+struct CubicResampler {
+    // Input samples consumed per output sample (in_rate / out_rate).
+    ratio: f64,
+    // Read position in the working buffer (history + current input).
+    pos: f64,
+    // Trailing input samples carried over from the previous call.
+    hist: Vec<f32>,
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    p1 + 0.5
+        * t
+        * ((p2 - p0) + t * ((2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) + t * (3.0 * (p1 - p2) + p3 - p0)))
+}
+
+impl CubicResampler {
+    fn new(in_rate: u32, out_rate: u32) -> Self {
+        Self {
+            ratio: in_rate as f64 / out_rate as f64,
+            // Start one sample in so the cubic kernel has a left neighbour.
+            pos: 1.0,
+            hist: Vec::new(),
+        }
+    }
+
+    fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut buf = Vec::with_capacity(self.hist.len() + input.len());
+        buf.extend_from_slice(&self.hist);
+        buf.extend_from_slice(input);
+
+        let mut output = Vec::new();
+        loop {
+            let i = self.pos.floor() as isize;
+            if i < 1 || (i as usize + 2) >= buf.len() {
+                break;
+            }
+            let t = (self.pos - i as f64) as f32;
+            let p0 = buf[(i - 1) as usize];
+            let p1 = buf[i as usize];
+            let p2 = buf[(i + 1) as usize];
+            let p3 = buf[(i + 2) as usize];
+            output.push(catmull_rom(p0, p1, p2, p3, t));
+            self.pos += self.ratio;
+        }
+
+        // Retain everything from one sample before the next read position so
+        // the kernel's left neighbour survives into the next call.
+        let keep_from = (self.pos.floor() as isize - 1).max(0) as usize;
+        let keep_from = keep_from.min(buf.len());
+        self.hist = buf[keep_from..].to_vec();
+        self.pos -= keep_from as f64;
+        output
+    }
+
+    // Emit any real samples still held in `hist` at end-of-stream. Without a
+    // following block the cubic kernel has no right neighbour for the last one
+    // or two input samples, so they'd never be produced; feed trailing zeros so
+    // those samples get interpolated out.
+    fn drain(&mut self) -> Vec<f32> {
+        self.process(&[0.0; 2])
+    }
+}
+
+// Denoiser that accepts audio at an arbitrary sample rate, resampling up to
+// the fixed 48 kHz internal rate for the frame pipeline and back down to the
+// caller's rate. History is retained across calls so a stream can be fed in
+// arbitrary chunks.
+#[wasm_bindgen]
+pub struct WasmDenoiseResampler {
+    upsampler: CubicResampler,
+    downsampler: CubicResampler,
+    denoiser: WasmDenoiseState,
+}
+
+#[wasm_bindgen]
+impl WasmDenoiseResampler {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: u32) -> Self {
+        if sample_rate == 0 {
+            panic!("Sample rate must be greater than 0");
+        }
+
+        Self {
+            upsampler: CubicResampler::new(sample_rate, INTERNAL_SAMPLE_RATE),
+            downsampler: CubicResampler::new(INTERNAL_SAMPLE_RATE, sample_rate),
+            denoiser: WasmDenoiseState::new(),
+        }
+    }
+
+    // Feed an arbitrary-length block at the configured sample rate and get back
+    // the denoised block at the same rate. Output length tracks the internal
+    // frame boundaries, so up to one internal frame (FRAME_SIZE samples at
+    // 48 kHz) may be deferred to the next call.
+    #[wasm_bindgen]
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let upsampled = self.upsampler.process(input);
+        let denoised = self.denoiser.push_samples(&upsampled);
+        self.downsampler.process(&denoised)
+    }
+
+    // Drain the internal frame buffer (zero-padding the final partial frame)
+    // and return the remaining denoised samples at the configured rate,
+    // including the upsampler's and downsampler's trailing history so the
+    // stream tail isn't dropped.
+    #[wasm_bindgen]
+    pub fn flush(&mut self) -> Vec<f32> {
+        let tail = self.upsampler.drain();
+        let mut denoised = self.denoiser.push_samples(&tail);
+        denoised.extend_from_slice(&self.denoiser.flush());
+        let mut output = self.downsampler.process(&denoised);
+        output.extend_from_slice(&self.downsampler.drain());
+        output
+    }
+}
+
+// One-shot convenience: denoise a whole buffer delivered at `sample_rate`,
+// resampling through the 48 kHz pipeline and back. The resampler is cubic with
+// no anti-alias lowpass, so downsampling back to any rate below 48 kHz can fold
+// noise residue above the output Nyquist into the band — fine for speech, see `CubicResampler`.
+#[wasm_bindgen(js_name = denoiseAudioChunkAt)]
+pub fn denoise_audio_chunk_at(input: &[f32], sample_rate: u32) -> Vec<f32> {
+    let mut resampler = WasmDenoiseResampler::new(sample_rate);
+    let mut output = resampler.process(input);
+    output.extend_from_slice(&resampler.flush());
+    output
+}
+
 // Export a simple denoise function for easier use
 #[wasm_bindgen]
 pub fn denoise_audio_chunk(input: &[f32]) -> Vec<f32> {
@@ -94,4 +513,84 @@ pub fn denoise_audio_chunk(input: &[f32]) -> Vec<f32> {
     }
     
     output
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resampler_identity_rate_preserves_signal() {
+        // At equal in/out rates the resampler is effectively a pass-through that
+        // skips the first input sample; the samples it emits should match the
+        // input (offset by the one-sample kernel warm-up), and a drain should
+        // flush the tail.
+        let mut r = CubicResampler::new(48_000, 48_000);
+        let input: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        let mut out = r.process(&input);
+        out.extend_from_slice(&r.drain());
+
+        // Every emitted sample lines up with an input sample.
+        for (k, &v) in out.iter().enumerate() {
+            assert!((v - input[k + 1]).abs() < 1e-4, "sample {} = {}", k, v);
+        }
+        // The tail is not dropped: we recover all but the warm-up sample.
+        assert_eq!(out.len(), input.len() - 1);
+    }
+
+    #[test]
+    fn resampler_downsample_length_is_bounded() {
+        // Downsampling 2:1 should emit roughly half as many samples, never more
+        // than the input, across chunked calls.
+        let mut r = CubicResampler::new(48_000, 24_000);
+        let mut total = 0;
+        for _ in 0..4 {
+            total += r.process(&vec![1.0; 480]).len();
+        }
+        total += r.drain().len();
+        assert!(total <= 4 * 480, "emitted {} samples", total);
+        assert!(total >= 4 * 480 / 2 - 4, "emitted {} samples", total);
+    }
+
+    #[test]
+    fn resampler_is_continuous_across_chunk_boundaries() {
+        // Feeding a ramp in one call vs. several small calls must produce the
+        // same output — the retained history prevents boundary discontinuities.
+        let ramp: Vec<f32> = (0..240).map(|i| i as f32).collect();
+
+        let mut whole = CubicResampler::new(44_100, 48_000);
+        let one = whole.process(&ramp);
+
+        let mut split = CubicResampler::new(44_100, 48_000);
+        let mut many = Vec::new();
+        for chunk in ramp.chunks(37) {
+            many.extend_from_slice(&split.process(chunk));
+        }
+
+        assert_eq!(one.len(), many.len());
+        for (a, b) in one.iter().zip(many.iter()) {
+            assert!((a - b).abs() < 1e-4, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn deinterleave_reinterleave_roundtrip_is_identity() {
+        let channels = 2;
+        let mut block = vec![0.0; channels * FRAME_SIZE];
+        for (i, v) in block.iter_mut().enumerate() {
+            *v = i as f32;
+        }
+
+        let mut rebuilt = vec![0.0; channels * FRAME_SIZE];
+        let mut frame = vec![0.0; FRAME_SIZE];
+        for ch in 0..channels {
+            deinterleave_frame(&block, channels, ch, &mut frame);
+            // Channel ch holds exactly the samples at indices ch, ch+2, ...
+            for i in 0..FRAME_SIZE {
+                assert_eq!(frame[i], (i * channels + ch) as f32);
+            }
+            interleave_frame(&frame, channels, ch, &mut rebuilt);
+        }
+
+        assert_eq!(block, rebuilt);
+    }
+}